@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+
+use log::info;
+
+use crate::config::configurator::{DedupMode, OverwriteMode};
+use crate::photo::Photo;
+use crate::pserror::error::*;
+
+/// Large dictionary/window size so the xz encoder can find cross-file
+/// redundancy even between already-compressed JPEGs from the same burst.
+const XZ_DICT_SIZE: u32 = 64 * 1024 * 1024;
+
+/// Single-file archive format a `--dest` path can ask us to stream sorted
+/// photos into, instead of writing a loose `year/month/day/` tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarXz,
+    TarZst,
+}
+
+/// Infers the archive format (if any) from `dest`'s extension.
+pub fn detect(dest: &str) -> Option<ArchiveFormat> {
+    if dest.ends_with(".tar.xz") {
+        Some(ArchiveFormat::TarXz)
+    } else if dest.ends_with(".tar.zst") {
+        Some(ArchiveFormat::TarZst)
+    } else if dest.ends_with(".tar") {
+        Some(ArchiveFormat::Tar)
+    } else if dest.ends_with(".zip") {
+        Some(ArchiveFormat::Zip)
+    } else {
+        None
+    }
+}
+
+/// Streams every photo in `photos` into a single archive at `dest`, using
+/// each photo's date-based internal path (`year/month/day/name`, already
+/// computed as its `new_path`) as the archive entry name.
+///
+/// `dedup`/`overwrite`/`interactive` are applied to the entry list before
+/// anything is written, mirroring the collision handling `move_photo` does
+/// for loose-directory destinations: archive entries can't be overwritten
+/// in place the way a file on disk can, so collisions are resolved up front
+/// instead.
+pub fn write_archive(
+    dest: &str,
+    format: ArchiveFormat,
+    photos: &[Photo],
+    compression_level: u32,
+    dedup: Option<DedupMode>,
+    overwrite: OverwriteMode,
+    interactive: bool,
+) -> Result<(), PsError> {
+    let entries = resolve_entries(photos, dedup, overwrite, interactive)?;
+    match format {
+        ArchiveFormat::Zip => write_zip(dest, &entries, compression_level),
+        ArchiveFormat::Tar => write_tar(File::create(dest)?, &entries),
+        ArchiveFormat::TarXz => {
+            let mut filters = xz2::stream::Filters::new();
+            let lzma_options = xz2::stream::LzmaOptions::new_preset(compression_level)
+                .map_err(|err| PsError::new(PsErrorKind::MediaError, err.to_string()))?
+                .dict_size(XZ_DICT_SIZE);
+            filters.lzma2(&lzma_options);
+            let stream =
+                xz2::stream::Stream::new_stream_encoder(&filters, xz2::stream::Check::Crc64)
+                    .map_err(|err| PsError::new(PsErrorKind::MediaError, err.to_string()))?;
+            let encoder = xz2::write::XzEncoder::new_stream(File::create(dest)?, stream);
+            write_tar(encoder, &entries)
+        }
+        ArchiveFormat::TarZst => {
+            let encoder = zstd::Encoder::new(File::create(dest)?, compression_level as i32)?
+                .auto_finish();
+            write_tar(encoder, &entries)
+        }
+    }
+}
+
+/// Applies dedup/overwrite/interactive collision handling to `photos`,
+/// returning the surviving photos paired with the entry name each should be
+/// written under. `--interactive` has no archive equivalent (there's no
+/// single file to prompt about mid-stream), so it falls back to the same
+/// numbered-entry-name behavior as `--overwrite=backup`, with a warning.
+/// Dedup here compares exact content digests only; near-duplicates (dHash)
+/// are not detected for archive destinations, and `--dedup=link` skips the
+/// duplicate entry like `--dedup=skip`.
+fn resolve_entries<'a>(
+    photos: &'a [Photo],
+    dedup: Option<DedupMode>,
+    overwrite: OverwriteMode,
+    interactive: bool,
+) -> Result<Vec<(&'a Photo, String)>, PsError> {
+    if interactive {
+        log::warn!(
+            "--interactive is not supported for archive destinations; \
+             colliding entries will be numbered instead of prompted for"
+        );
+    }
+
+    let mut seen_digests: HashMap<blake3::Hash, String> = HashMap::new();
+    let mut used_names: HashSet<String> = HashSet::new();
+    let mut resolved = Vec::with_capacity(photos.len());
+
+    for photo in photos {
+        let name = default_entry_name(photo)?.to_string();
+
+        if dedup.is_some() {
+            let digest = photo.content_digest()?;
+            if let Some(existing) = seen_digests.get(&digest) {
+                info!("Skipping duplicate of {} in archive: {}", existing, name);
+                continue;
+            }
+            seen_digests.insert(digest, name.clone());
+        }
+
+        let resolved_name = if used_names.contains(&name) {
+            if !interactive && overwrite == OverwriteMode::NoClobber {
+                info!(
+                    "Entry name collision, skipping due to --overwrite=noclobber: {}",
+                    name
+                );
+                continue;
+            }
+            let unique = numbered_entry_name(&name, &used_names);
+            info!(
+                "Entry name collision for {}, writing as {} instead",
+                name, unique
+            );
+            unique
+        } else {
+            name
+        };
+
+        used_names.insert(resolved_name.clone());
+        resolved.push((photo, resolved_name));
+    }
+
+    Ok(resolved)
+}
+
+/// Returns the lowest-numbered free `name.~N~` variant of `name`, mirroring
+/// `numbered_suffix_path`'s scheme for loose-directory destinations.
+fn numbered_entry_name(name: &str, used: &HashSet<String>) -> String {
+    let mut index = 1u64;
+    loop {
+        let candidate = format!("{}.~{}~", name, index);
+        if !used.contains(&candidate) {
+            return candidate;
+        }
+        index += 1;
+    }
+}
+
+fn write_zip(dest: &str, entries: &[(&Photo, String)], compression_level: u32) -> Result<(), PsError> {
+    let mut zip = zip::ZipWriter::new(File::create(dest)?);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .compression_level(Some(compression_level as i64));
+
+    for (photo, entry_name) in entries {
+        let source = source_path(photo)?;
+        zip.start_file(entry_name, options)
+            .map_err(|err| PsError::new(PsErrorKind::MediaError, err.to_string()))?;
+        zip.write_all(&std::fs::read(source)?)?;
+    }
+
+    zip.finish()
+        .map_err(|err| PsError::new(PsErrorKind::MediaError, err.to_string()))?;
+    Ok(())
+}
+
+fn write_tar<W: Write>(writer: W, entries: &[(&Photo, String)]) -> Result<(), PsError> {
+    let mut builder = tar::Builder::new(writer);
+    for (photo, entry_name) in entries {
+        let source = source_path(photo)?;
+        builder.append_path_with_name(source, entry_name)?;
+    }
+    builder.into_inner()?;
+    Ok(())
+}
+
+fn source_path(photo: &Photo) -> Result<&str, PsError> {
+    photo
+        .path()
+        .as_ref()
+        .map(String::as_str)
+        .ok_or_else(|| PsError::new(PsErrorKind::IoError, "photo has no source path".to_string()))
+}
+
+fn default_entry_name(photo: &Photo) -> Result<&str, PsError> {
+    photo.new_path().as_ref().map(String::as_str).ok_or_else(|| {
+        PsError::new(
+            PsErrorKind::IoError,
+            "photo has no destination path".to_string(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_matches_known_suffixes() {
+        assert_eq!(detect("out.zip"), Some(ArchiveFormat::Zip));
+        assert_eq!(detect("out.tar"), Some(ArchiveFormat::Tar));
+        assert_eq!(detect("out.tar.xz"), Some(ArchiveFormat::TarXz));
+        assert_eq!(detect("out.tar.zst"), Some(ArchiveFormat::TarZst));
+        assert_eq!(detect("out/plain/dir"), None);
+    }
+
+    #[test]
+    fn detect_prefers_longer_tar_suffixes() {
+        // .tar.xz and .tar.zst both end in .tar; make sure those are checked
+        // before the bare .tar case.
+        assert_eq!(detect("photos.tar.xz"), Some(ArchiveFormat::TarXz));
+        assert_eq!(detect("photos.tar.zst"), Some(ArchiveFormat::TarZst));
+    }
+
+    #[test]
+    fn numbered_entry_name_picks_lowest_free_index() {
+        let mut used = HashSet::new();
+        used.insert("2024/01/01/photo.jpg.~1~".to_string());
+        let picked = numbered_entry_name("2024/01/01/photo.jpg", &used);
+        assert_eq!(picked, "2024/01/01/photo.jpg.~2~");
+    }
+
+    #[test]
+    fn resolve_entries_numbers_colliding_names_by_default() {
+        let mut a = Photo::new("a.jpg".to_string(), None);
+        a.set_new_path("2024/01/01/photo.jpg".to_string());
+        let mut b = Photo::new("b.jpg".to_string(), None);
+        b.set_new_path("2024/01/01/photo.jpg".to_string());
+
+        let resolved = resolve_entries(&[a, b], None, OverwriteMode::Clobber, false).unwrap();
+
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].1, "2024/01/01/photo.jpg");
+        assert_eq!(resolved[1].1, "2024/01/01/photo.jpg.~1~");
+    }
+
+    #[test]
+    fn resolve_entries_skips_colliding_names_under_noclobber() {
+        let mut a = Photo::new("a.jpg".to_string(), None);
+        a.set_new_path("2024/01/01/photo.jpg".to_string());
+        let mut b = Photo::new("b.jpg".to_string(), None);
+        b.set_new_path("2024/01/01/photo.jpg".to_string());
+
+        let resolved = resolve_entries(&[a, b], None, OverwriteMode::NoClobber, false).unwrap();
+
+        assert_eq!(resolved.len(), 1);
+    }
+}
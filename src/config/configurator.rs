@@ -0,0 +1,182 @@
+use std::env;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DedupMode {
+    Skip,
+    Link,
+}
+
+/// What to do when a photo's destination path is already occupied by
+/// another file.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum OverwriteMode {
+    /// Overwrite the existing file, as `move_photo` has always done.
+    Clobber,
+    /// Leave the existing file untouched and skip the incoming photo.
+    NoClobber,
+    /// Rename the existing file out of the way to a numbered backup
+    /// (`name.jpg.~1~`, `~2~`, ...) before writing the incoming photo.
+    Backup,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Config {
+    pub source: String,
+    pub destination: String,
+    pub copy: bool,
+    pub dry_run: bool,
+    pub logfile: Option<String>,
+    pub dedup: Option<DedupMode>,
+    pub dhash_threshold: u32,
+    pub overwrite: OverwriteMode,
+    pub threads: usize,
+    pub thumbnails: bool,
+    pub compression_level: u32,
+    pub interactive: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            source: String::new(),
+            destination: String::new(),
+            copy: false,
+            dry_run: false,
+            logfile: None,
+            dedup: None,
+            dhash_threshold: 5,
+            overwrite: OverwriteMode::Clobber,
+            threads: default_thread_count(),
+            thumbnails: false,
+            compression_level: 6,
+            interactive: false,
+        }
+    }
+}
+
+fn default_thread_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+pub fn print_help() {
+    println!("photosort --src <dir> --dest <dir> [options]");
+    println!();
+    println!("Options:");
+    println!("  --src <dir>             Source directory or .zip archive to import from");
+    println!("  --dest <dir>            Destination directory to sort photos into");
+    println!("  --copy                  Copy files instead of moving them");
+    println!("  --dry-run               Don't touch the filesystem, just log what would happen");
+    println!("  --logfile <path>        Write logs to the given file instead of stderr");
+    println!("  --dedup=skip|link       Skip or hard-link exact and near-duplicate photos");
+    println!("  --dhash-threshold <n>   Max Hamming distance for near-duplicate photos (default: 5)");
+    println!("  --overwrite=<mode>      clobber (default), noclobber, or backup");
+    println!("  --threads <n>           Worker threads for moving/copying (default: CPU count)");
+    println!("  --thumbnails            Write a JPEG thumbnail alongside each sorted video");
+    println!("  --compression-level <n> Compression level for .zip/.tar.xz/.tar.zst destinations (default: 6)");
+    println!("  --interactive, -i       Prompt before overwriting an existing destination file");
+    println!();
+    println!("If --dest ends in .zip, .tar, .tar.xz, or .tar.zst, photos are streamed into");
+    println!("a single archive using their year/month/day path as the entry name, instead");
+    println!("of being written out as a loose directory tree.");
+}
+
+pub fn get_config(args: Option<Vec<String>>) -> Result<Config, Box<dyn std::error::Error>> {
+    let args: Vec<String> = args.unwrap_or_else(|| env::args().skip(1).collect());
+
+    let mut config = Config::default();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--src" => config.source = iter.next().ok_or("--src requires a value")?.clone(),
+            "--dest" => config.destination = iter.next().ok_or("--dest requires a value")?.clone(),
+            "--copy" => config.copy = true,
+            "--dry-run" => config.dry_run = true,
+            "--logfile" => config.logfile = Some(iter.next().ok_or("--logfile requires a value")?.clone()),
+            other if other.starts_with("--dedup=") => {
+                config.dedup = Some(match &other["--dedup=".len()..] {
+                    "skip" => DedupMode::Skip,
+                    "link" => DedupMode::Link,
+                    value => return Err(format!("Unknown --dedup mode: {}", value).into()),
+                });
+            }
+            other if other.starts_with("--overwrite=") => {
+                config.overwrite = match &other["--overwrite=".len()..] {
+                    "clobber" => OverwriteMode::Clobber,
+                    "noclobber" => OverwriteMode::NoClobber,
+                    "backup" => OverwriteMode::Backup,
+                    value => return Err(format!("Unknown --overwrite mode: {}", value).into()),
+                };
+            }
+            "--threads" => {
+                let value = iter.next().ok_or("--threads requires a value")?;
+                config.threads = value.parse().map_err(|_| format!("Invalid --threads value: {}", value))?;
+            }
+            "--dhash-threshold" => {
+                let value = iter.next().ok_or("--dhash-threshold requires a value")?;
+                config.dhash_threshold = value
+                    .parse()
+                    .map_err(|_| format!("Invalid --dhash-threshold value: {}", value))?;
+            }
+            "--thumbnails" => config.thumbnails = true,
+            "--interactive" | "-i" => config.interactive = true,
+            "--compression-level" => {
+                let value = iter.next().ok_or("--compression-level requires a value")?;
+                config.compression_level = value
+                    .parse()
+                    .map_err(|_| format!("Invalid --compression-level value: {}", value))?;
+            }
+            _ => return Err(format!("Unrecognized argument: {}", arg).into()),
+        }
+    }
+
+    if config.source.is_empty() || config.destination.is_empty() {
+        return Err(crate::error_messages::BOTH_MUST_BE_PROVIDED.into());
+    }
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn dhash_threshold_defaults_to_five() {
+        let config = get_config(args(&["--src", "in", "--dest", "out"])).unwrap();
+        assert_eq!(config.dhash_threshold, 5);
+    }
+
+    #[test]
+    fn dhash_threshold_flag_overrides_default() {
+        let config = get_config(args(&[
+            "--src",
+            "in",
+            "--dest",
+            "out",
+            "--dhash-threshold",
+            "10",
+        ]))
+        .unwrap();
+        assert_eq!(config.dhash_threshold, 10);
+    }
+
+    #[test]
+    fn dhash_threshold_rejects_non_numeric_value() {
+        let result = get_config(args(&[
+            "--src",
+            "in",
+            "--dest",
+            "out",
+            "--dhash-threshold",
+            "not-a-number",
+        ]));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::Path;
+
+use log::warn;
+
+use crate::photo::Photo;
+
+const PHOTO_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "tiff", "mp4", "mov", "avi", "mkv", "heic", "heif", "cr2",
+    "nef", "arw", "dng",
+];
+
+const HEIF_EXTENSIONS: &[&str] = &["heic", "heif"];
+const RAW_EXTENSIONS: &[&str] = &["cr2", "nef", "arw", "dng"];
+
+/// Recursively lists every file under `src` whose extension looks like a
+/// photo or video we know how to sort.
+pub fn list_all_files(src: &String) -> Vec<String> {
+    let mut result = Vec::new();
+    visit(Path::new(src), &mut result);
+    result
+}
+
+fn visit(dir: &Path, out: &mut Vec<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            visit(&path, out);
+        } else if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            if PHOTO_EXTENSIONS.contains(&ext.to_lowercase().as_str()) {
+                out.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+}
+
+/// Turns a flat list of file paths into `Photo`s. HEIF and RAW files take
+/// the capture date from their embedded EXIF; every other format, and any
+/// HEIF/RAW file without a readable embedded date, uses the filesystem mtime.
+pub fn process_raw_files(file_list: &Vec<String>) -> Vec<Photo> {
+    file_list
+        .iter()
+        .map(|path| Photo::new(path.clone(), extract_date(path)))
+        .collect()
+}
+
+fn extract_date(path: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    if let Some(ext) = ext.as_deref() {
+        if HEIF_EXTENSIONS.contains(&ext) {
+            if let Some(date) = extract_heif_date(path) {
+                return Some(date);
+            }
+        } else if RAW_EXTENSIONS.contains(&ext) {
+            if let Some(date) = extract_raw_date(path) {
+                return Some(date);
+            }
+        }
+    }
+
+    // TODO: pull the real capture date out of EXIF/video metadata for the
+    // remaining formats; for now fall back to the file's last-modified time.
+    fs::metadata(path)
+        .ok()
+        .and_then(|meta| meta.modified().ok())
+        .map(|time| time.into())
+}
+
+/// Pulls the EXIF capture date out of a HEIC/HEIF container. Requires the
+/// `heif` cargo feature; without it we fall back to filesystem mtime and
+/// log that the decoder isn't available, so that fallback isn't confused
+/// with "this file just has no EXIF date".
+#[cfg(feature = "heif")]
+fn extract_heif_date(path: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let ctx = libheif_rs::HeifContext::read_from_file(path).ok()?;
+    let handle = ctx.primary_image_handle().ok()?;
+    let exif = handle.metadata("Exif")?;
+    crate::photo::exif::parse_capture_date(&exif)
+}
+
+#[cfg(not(feature = "heif"))]
+fn extract_heif_date(path: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    warn!(
+        "Built without the \"heif\" feature; falling back to mtime for {}",
+        path
+    );
+    None
+}
+
+/// Pulls the embedded capture timestamp out of a RAW file (CR2/NEF/ARW/DNG).
+/// Requires the `raw` cargo feature; without it we fall back to filesystem
+/// mtime and log that the decoder isn't available, for the same reason as
+/// `extract_heif_date`.
+#[cfg(feature = "raw")]
+fn extract_raw_date(path: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let raw_image = rawloader::decode_file(path).ok()?;
+    crate::photo::exif::parse_capture_date(raw_image.exif.as_ref()?)
+}
+
+#[cfg(not(feature = "raw"))]
+fn extract_raw_date(path: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    warn!(
+        "Built without the \"raw\" feature; falling back to mtime for {}",
+        path
+    );
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heif_and_raw_extensions_are_disjoint_subsets_of_photo_extensions() {
+        for ext in HEIF_EXTENSIONS {
+            assert!(PHOTO_EXTENSIONS.contains(ext));
+            assert!(!RAW_EXTENSIONS.contains(ext));
+        }
+        for ext in RAW_EXTENSIONS {
+            assert!(PHOTO_EXTENSIONS.contains(ext));
+        }
+    }
+
+    #[test]
+    fn extract_date_falls_back_to_mtime_without_heif_raw_features() {
+        let dir = std::env::temp_dir().join(format!("photosort-test-discovery-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("photo.heic");
+        fs::write(&path, b"not a real heic file").unwrap();
+
+        let date = extract_date(path.to_str().unwrap());
+        assert!(date.is_some());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
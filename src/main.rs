@@ -1,21 +1,28 @@
 extern crate ffmpeg_next as ffmpeg;
 
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use chrono::Datelike;
 
 use indicatif::{ProgressBar, ProgressStyle};
 use log::LevelFilter;
 use log::{info, warn};
+use rayon::prelude::*;
 
 use crate::pserror::error::*;
-use config::configurator::{get_config, Config};
-use photo::Photo;
+use config::configurator::{get_config, Config, DedupMode, OverwriteMode};
+use photo::{DHash, DedupTracker, Photo};
 
+mod archive;
 mod config;
 mod discovery;
 mod photo;
 mod pserror;
+mod thumbnails;
 mod zipfiles;
 
 mod error_messages {
@@ -62,9 +69,47 @@ fn convert_files(config: &Config) {
         let file_list = discovery::discovery::list_all_files(&config.source);
         let mut photo_list = discovery::discovery::process_raw_files(&file_list);
         info!("Produced a list of {} files", photo_list.len());
-        update_new_path(&config.destination, &mut photo_list);
+
+        let archive_format = archive::detect(&config.destination);
+        let dest_prefix = if archive_format.is_some() {
+            Option::None
+        } else {
+            Some(config.destination.as_str())
+        };
+        update_new_path(dest_prefix, &mut photo_list);
         info!("Updated a list of {} files", file_list.len());
-        let bar = ProgressBar::new(file_list.len() as u64);
+
+        if let Some(format) = archive_format {
+            if config.dry_run {
+                info!(
+                    "Dry-run, not writing {:?} archive: {}",
+                    format, config.destination
+                );
+                return;
+            }
+
+            info!(
+                "Streaming {} sorted photo(s) into a single {:?} archive: {}",
+                photo_list.len(),
+                format,
+                config.destination
+            );
+            match archive::write_archive(
+                &config.destination,
+                format,
+                &photo_list,
+                config.compression_level,
+                config.dedup,
+                config.overwrite,
+                config.interactive,
+            ) {
+                Ok(_) => info!("Wrote archive {}", config.destination),
+                Err(err) => warn!("Failed to write archive {}: {}", config.destination, err),
+            }
+            return;
+        }
+
+        let bar = Arc::new(ProgressBar::new(file_list.len() as u64));
 
         bar.set_message("Moving/copying files ... ");
         bar.set_style(
@@ -72,32 +117,283 @@ fn convert_files(config: &Config) {
                 .template("[{elapsed_precise}] {bar:80.green/red} {pos:>7}/{len:7} {msg}")
                 .progress_chars("??????"),
         );
-        for photo in photo_list {
-            bar.inc(1);
-            match move_photo(&photo, !config.copy, config.dry_run) {
-                Ok(_) => {
-                    info!(
-                        "Moved photo {} -> {}",
-                        photo.path().as_ref().unwrap(),
-                        photo.new_path().as_ref().unwrap()
-                    );
+
+        let dedup = Mutex::new(DedupTracker::new());
+        let dirs = DirCache::new();
+        let path_locks = PathLocks::new();
+        let duplicate_count = AtomicU64::new(0);
+        let failure_count = AtomicU64::new(0);
+        let interactive = config.interactive.then(InteractivePrompt::new);
+
+        // Prompting reads from stdin, so interactive runs are single-threaded;
+        // otherwise answers from different workers could interleave.
+        let threads = if config.interactive { 1 } else { config.threads };
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed to start worker pool");
+
+        pool.install(|| {
+            photo_list.par_iter().for_each(|photo| {
+                bar.inc(1);
+
+                // Fingerprinted but not yet a known duplicate: recorded into
+                // `dedup` only once the move below actually succeeds, so a
+                // failed write can't poison later true duplicates against a
+                // destination that was never written.
+                let mut pending_fingerprint: Option<(blake3::Hash, Option<DHash>)> = None;
+
+                if let Some(mode) = config.dedup {
+                    match dedup_lookup(photo, &dedup, config.dhash_threshold) {
+                        Ok(lookup) => match lookup.existing {
+                            Some(existing) => {
+                                duplicate_count.fetch_add(1, Ordering::Relaxed);
+                                if config.dry_run {
+                                    info!(
+                                        "Dry-run, not touching duplicate of {}: {}",
+                                        existing.display(),
+                                        photo.path().as_ref().unwrap()
+                                    );
+                                    return;
+                                }
+                                match mode {
+                                    DedupMode::Skip => info!(
+                                        "Skipping duplicate of {}: {}",
+                                        existing.display(),
+                                        photo.path().as_ref().unwrap()
+                                    ),
+                                    DedupMode::Link => match link_duplicate(&existing, photo, &dirs) {
+                                        Ok(_) => info!(
+                                            "Hard-linked duplicate {} -> {}",
+                                            existing.display(),
+                                            photo.new_path().as_ref().unwrap()
+                                        ),
+                                        Err(err) => warn!(
+                                            "Failed to hard-link duplicate {:?}: {}",
+                                            photo.path(),
+                                            err
+                                        ),
+                                    },
+                                }
+                                return;
+                            }
+                            None => pending_fingerprint = Some((lookup.digest, lookup.dhash)),
+                        },
+                        Err(err) => {
+                            warn!("Failed to fingerprint {:?}: {}", photo.path(), err);
+                        }
+                    }
                 }
-                Err(err) => {
-                    warn!("Failed to move photo {:?}: {}", photo.path(), err);
+
+                match move_photo(
+                    photo,
+                    !config.copy,
+                    config.dry_run,
+                    config.overwrite,
+                    &dirs,
+                    &path_locks,
+                    interactive.as_ref(),
+                    &bar,
+                ) {
+                    Ok(_) => {
+                        if let Some((digest, dhash)) = pending_fingerprint {
+                            if !config.dry_run {
+                                let dest = PathBuf::from(photo.new_path().as_ref().unwrap());
+                                let mut dedup = dedup.lock().unwrap();
+                                dedup.record_digest(digest, dest.clone());
+                                if let Some(hash) = dhash {
+                                    dedup.record_hash(hash, dest);
+                                }
+                            }
+                        }
+                        info!(
+                            "Moved photo {} -> {}",
+                            photo.path().as_ref().unwrap(),
+                            photo.new_path().as_ref().unwrap()
+                        );
+                        if config.thumbnails && !config.dry_run && is_video(photo) {
+                            if let Err(err) = thumbnails::generate_thumbnail(photo, &config.destination) {
+                                warn!("Failed to generate thumbnail for {:?}: {}", photo.path(), err);
+                            }
+                        } else if config.thumbnails && !config.dry_run {
+                            if let Some(companion) = live_photo_companion(photo) {
+                                if let Err(err) = thumbnails::generate_thumbnail_from(
+                                    &companion.to_string_lossy(),
+                                    photo,
+                                    &config.destination,
+                                ) {
+                                    warn!(
+                                        "Failed to generate thumbnail for Live Photo companion {:?}: {}",
+                                        companion, err
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        failure_count.fetch_add(1, Ordering::Relaxed);
+                        warn!("Failed to move photo {:?}: {}", photo.path(), err);
+                    }
                 }
-            }
-        }
+            });
+        });
+
         bar.finish();
+        if config.dedup.is_some() {
+            info!(
+                "Detected {} duplicate photo(s)",
+                duplicate_count.load(Ordering::Relaxed)
+            );
+        }
+        let failures = failure_count.load(Ordering::Relaxed);
+        if failures > 0 {
+            warn!("{} photo(s) failed to move/copy", failures);
+        }
+    }
+}
+
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov"];
+
+/// True when `photo`'s source file is itself a video we know how to pull a
+/// thumbnail frame out of. Apple Live Photos (a still image with a same-named
+/// `.mov` sibling) are handled separately by `live_photo_companion`, since
+/// there the still, not the video, is what gets sorted. Android/Samsung
+/// "Motion Photo" JPEGs (video embedded inside the JPEG itself) aren't
+/// detected by either path and are out of scope for now.
+fn is_video(photo: &Photo) -> bool {
+    photo
+        .path()
+        .as_ref()
+        .and_then(|path| Path::new(path).extension())
+        .and_then(|ext| ext.to_str())
+        .map(|ext| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Looks for an Apple Live Photo's motion video: a `.mov`/`.MOV` file with
+/// the same name (minus extension) next to `photo`'s source file. Used so
+/// `--thumbnails` can pull a frame from the motion video even though the
+/// still image (the one actually getting sorted) isn't itself a video.
+fn live_photo_companion(photo: &Photo) -> Option<PathBuf> {
+    let path = Path::new(photo.path().as_ref()?);
+    let stem = path.file_stem()?.to_str()?;
+    let dir = path.parent()?;
+    for ext in ["mov", "MOV"] {
+        let candidate = dir.join(format!("{}.{}", stem, ext));
+        if candidate.exists() {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// De-duplicates `create_dir_all` calls across worker threads so that many
+/// photos landing in the same `year/month/day` folder don't race each other
+/// creating it.
+struct DirCache(Mutex<HashSet<PathBuf>>);
+
+impl DirCache {
+    fn new() -> Self {
+        DirCache(Mutex::new(HashSet::new()))
+    }
+
+    fn ensure(&self, dir: &Path) -> std::io::Result<()> {
+        let mut created = self.0.lock().unwrap();
+        if created.contains(dir) {
+            return Ok(());
+        }
+        if !dir.exists() {
+            std::fs::create_dir_all(dir)?;
+        }
+        created.insert(dir.to_path_buf());
+        Ok(())
+    }
+}
+
+/// Serializes the exists-check/overwrite-resolution/write sequence for a
+/// given destination path across worker threads. Without this, two photos
+/// that resolve to the same `year/month/day/name` (e.g. two cameras on the
+/// same day producing the same file name) can both observe the destination
+/// as absent and race a `rename`/`copy` into it, or both race the
+/// lowest-free-index scan of `backup_existing` under `--overwrite=backup` or
+/// of `numbered_suffix_path` under `--interactive` rename.
+struct PathLocks(Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>);
+
+impl PathLocks {
+    fn new() -> Self {
+        PathLocks(Mutex::new(HashMap::new()))
+    }
+
+    /// Runs `f` while holding the lock for `path`, blocking any other thread
+    /// that's concurrently trying to resolve the same destination path.
+    fn with_lock<T>(&self, path: &Path, f: impl FnOnce() -> T) -> T {
+        let per_path = {
+            let mut locks = self.0.lock().unwrap();
+            locks
+                .entry(path.to_path_buf())
+                .or_insert_with(|| Arc::new(Mutex::new(())))
+                .clone()
+        };
+        let _guard = per_path.lock().unwrap();
+        f()
+    }
+}
+
+/// The fingerprint of a photo not yet known to be a duplicate, plus (if one
+/// was found) the path it's a duplicate of. The fingerprint is returned so
+/// the caller can record it *after* the move/copy actually succeeds, rather
+/// than here.
+struct DedupLookup {
+    digest: blake3::Hash,
+    dhash: Option<DHash>,
+    existing: Option<PathBuf>,
+}
+
+/// Fingerprints `photo` and asks `dedup` whether an identical (or, for
+/// images, near-identical) file has already been placed this run. The
+/// content digest (a full file read) and the perceptual dHash (a full image
+/// decode) are computed here, outside any lock, so the `dedup` mutex is only
+/// ever held for the cheap hashmap lookup itself.
+fn dedup_lookup(
+    photo: &Photo,
+    dedup: &Mutex<DedupTracker>,
+    dhash_threshold: u32,
+) -> std::io::Result<DedupLookup> {
+    let digest = photo.content_digest()?;
+    let dhash = photo.compute_dhash();
+
+    let existing = {
+        let dedup = dedup.lock().unwrap();
+        dedup
+            .lookup_digest(&digest)
+            .or_else(|| dhash.and_then(|hash| dedup.lookup_near_duplicate(&hash, dhash_threshold)))
+    };
+
+    Ok(DedupLookup {
+        digest,
+        dhash,
+        existing,
+    })
+}
+
+fn link_duplicate(existing: &Path, photo: &Photo, dirs: &DirCache) -> std::io::Result<()> {
+    let new_path = photo.new_path().as_ref().unwrap();
+    if let Some(dir) = Path::new(new_path).parent() {
+        dirs.ensure(dir)?;
     }
+    std::fs::hard_link(existing, new_path)
 }
 
-fn update_new_path(dest_dir: &String, photos: &mut Vec<Photo>) {
+fn update_new_path(dest_dir: Option<&str>, photos: &mut Vec<Photo>) {
     for photo in photos {
         update_photo_new_path(dest_dir, photo, Option::None)
     }
 }
 
-fn update_photo_new_path(dest_dir: &String, photo: &mut Photo, original_name: Option<&str>) {
+/// Computes a photo's sorted destination path. When `dest_dir` is `None`
+/// (streaming into a single archive instead of a directory tree), the path
+/// is just the date-based internal entry name with no filesystem prefix.
+fn update_photo_new_path(dest_dir: Option<&str>, photo: &mut Photo, original_name: Option<&str>) {
     let existing_path = Path::new(photo.path().as_ref().unwrap());
     match existing_path.file_name() {
         None => {
@@ -117,40 +413,252 @@ fn update_photo_new_path(dest_dir: &String, photo: &mut Photo, original_name: Op
 
             // photo must have valid date at this point.
             let date = photo.date().unwrap();
-            let path = format!(
-                "{}/{}/{:02}/{:02}/{}",
-                dest_dir,
-                date.year(),
-                date.month(),
-                date.day(),
-                new_name // should be safe (why?)
-            );
+            let path = match dest_dir {
+                Some(dest_dir) => format!(
+                    "{}/{}/{:02}/{:02}/{}",
+                    dest_dir,
+                    date.year(),
+                    date.month(),
+                    date.day(),
+                    new_name // should be safe (why?)
+                ),
+                None => format!(
+                    "{}/{:02}/{:02}/{}",
+                    date.year(),
+                    date.month(),
+                    date.day(),
+                    new_name
+                ),
+            };
 
             photo.set_new_path(path);
         }
     }
 }
 
-fn move_photo(photo: &Photo, move_file: bool, dry_run: bool) -> Result<(), PsError> {
+/// True when `existing` already looks like a copy of `original`: same size
+/// and same mtime. Used to silently skip re-imports of the same photo
+/// regardless of the configured overwrite mode.
+fn already_imported(original: &str, existing: &Path) -> bool {
+    let original_meta = match std::fs::metadata(original) {
+        Ok(meta) => meta,
+        Err(_) => return false,
+    };
+    let existing_meta = match std::fs::metadata(existing) {
+        Ok(meta) => meta,
+        Err(_) => return false,
+    };
+
+    original_meta.len() == existing_meta.len()
+        && match (original_meta.modified(), existing_meta.modified()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+}
+
+/// Renames `path` to the lowest-numbered free `path.~N~` backup, mirroring
+/// GNU mv's numbered-backup behavior.
+fn backup_existing(path: &Path) -> Result<(), PsError> {
+    let mut index = 1u64;
+    loop {
+        let candidate = format!("{}.~{}~", path.display(), index);
+        if !Path::new(&candidate).exists() {
+            std::fs::rename(path, &candidate)?;
+            return Ok(());
+        }
+        index += 1;
+    }
+}
+
+/// Returns the lowest-numbered free `path.~N~` variant of `path`, used to
+/// rename an *incoming* file out of the way of an existing one in
+/// `--interactive` mode.
+fn numbered_suffix_path(path: &Path) -> PathBuf {
+    let mut index = 1u64;
+    loop {
+        let candidate = PathBuf::from(format!("{}.~{}~", path.display(), index));
+        if !candidate.exists() {
+            return candidate;
+        }
+        index += 1;
+    }
+}
+
+/// What the user decided to do about an existing destination file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InteractiveChoice {
+    Overwrite,
+    Skip,
+    Rename,
+}
+
+/// Like coreutils `cp -i`: prompts before overwriting an existing
+/// destination file, offering per-file choices as well as "all" answers
+/// that are remembered for the rest of the run.
+struct InteractivePrompt {
+    remembered: Mutex<Option<InteractiveChoice>>,
+}
+
+impl InteractivePrompt {
+    fn new() -> Self {
+        InteractivePrompt {
+            remembered: Mutex::new(None),
+        }
+    }
+
+    fn resolve(&self, target_path: &Path, bar: &ProgressBar) -> InteractiveChoice {
+        if let Some(choice) = *self.remembered.lock().unwrap() {
+            return choice;
+        }
+
+        loop {
+            let mut answer = String::new();
+            bar.suspend(|| {
+                print!(
+                    "{} already exists. Overwrite? [y]es/[n]o/[r]ename/overwrite-[a]ll/skip-a[l]l: ",
+                    target_path.display()
+                );
+                let _ = std::io::stdout().flush();
+                let _ = std::io::stdin().read_line(&mut answer);
+            });
+
+            match parse_prompt_answer(&answer) {
+                Some(ParsedAnswer::Overwrite) => return InteractiveChoice::Overwrite,
+                Some(ParsedAnswer::Skip) => return InteractiveChoice::Skip,
+                Some(ParsedAnswer::Rename) => return InteractiveChoice::Rename,
+                Some(ParsedAnswer::OverwriteAll) => {
+                    *self.remembered.lock().unwrap() = Some(InteractiveChoice::Overwrite);
+                    return InteractiveChoice::Overwrite;
+                }
+                Some(ParsedAnswer::SkipAll) => {
+                    *self.remembered.lock().unwrap() = Some(InteractiveChoice::Skip);
+                    return InteractiveChoice::Skip;
+                }
+                None => continue,
+            }
+        }
+    }
+}
+
+/// One parsed answer to the overwrite prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParsedAnswer {
+    Overwrite,
+    Skip,
+    Rename,
+    OverwriteAll,
+    SkipAll,
+}
+
+/// Parses a raw line of input from the overwrite prompt. Deliberately does
+/// *not* accept the bare word "all": the prompt reads
+/// "overwrite-[a]ll/skip-a[l]l", so a user who typed the word "all" after
+/// reading the skip-all half of that hint used to get overwrite-all instead
+/// (`"a" | "all"` was the match arm for overwrite-all). Only the bracketed
+/// single-letter shortcuts and the unambiguous full phrases are accepted.
+fn parse_prompt_answer(answer: &str) -> Option<ParsedAnswer> {
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(ParsedAnswer::Overwrite),
+        "n" | "no" => Some(ParsedAnswer::Skip),
+        "r" | "rename" => Some(ParsedAnswer::Rename),
+        "a" | "overwrite-all" => Some(ParsedAnswer::OverwriteAll),
+        "l" | "skip-all" => Some(ParsedAnswer::SkipAll),
+        _ => None,
+    }
+}
+
+fn move_photo(
+    photo: &Photo,
+    move_file: bool,
+    dry_run: bool,
+    overwrite: OverwriteMode,
+    dirs: &DirCache,
+    locks: &PathLocks,
+    interactive: Option<&InteractivePrompt>,
+    bar: &ProgressBar,
+) -> Result<(), PsError> {
     let new_path = photo.new_path().as_ref().unwrap();
+    let canonical_path = PathBuf::from(new_path);
 
-    let full_path = Path::new(new_path);
-    let dir = match full_path.parent() {
+    let dir = match canonical_path.parent() {
         None => {
             return Err(PsError::new(
                 PsErrorKind::IoError,
                 format!("No parent directory for {}", new_path),
             ));
         }
-        Some(dir) => dir,
+        Some(dir) => dir.to_path_buf(),
     };
 
-    if !dir.exists() {
-        match std::fs::create_dir_all(dir) {
-            Err(err) => {
-                return Err(err.into());
-            }
-            _ => {}
+    dirs.ensure(&dir)?;
+
+    // Locked on the canonical (pre-rename) path: that's the path two racing
+    // photos would actually collide on.
+    locks.with_lock(&canonical_path, || {
+        move_photo_locked(
+            photo,
+            canonical_path,
+            &dir,
+            move_file,
+            dry_run,
+            overwrite,
+            dirs,
+            interactive,
+            bar,
+        )
+    })
+}
+
+fn move_photo_locked(
+    photo: &Photo,
+    mut target_path: PathBuf,
+    dir: &Path,
+    move_file: bool,
+    dry_run: bool,
+    overwrite: OverwriteMode,
+    dirs: &DirCache,
+    interactive: Option<&InteractivePrompt>,
+    bar: &ProgressBar,
+) -> Result<(), PsError> {
+    if target_path.exists() {
+        let original_path = photo.path().as_ref().unwrap();
+        if already_imported(original_path, &target_path) {
+            info!(
+                "Destination already matches source, skipping: {}",
+                target_path.display()
+            );
+            return Ok(());
+        }
+
+        match interactive {
+            Some(prompt) => match prompt.resolve(&target_path, bar) {
+                InteractiveChoice::Overwrite => {}
+                InteractiveChoice::Skip => {
+                    info!("Skipping at user's request: {}", target_path.display());
+                    return Ok(());
+                }
+                InteractiveChoice::Rename => {
+                    target_path = numbered_suffix_path(&target_path);
+                    dirs.ensure(dir)?;
+                }
+            },
+            None => match overwrite {
+                OverwriteMode::Clobber => {}
+                OverwriteMode::NoClobber => {
+                    info!(
+                        "Destination exists, skipping due to --overwrite=noclobber: {}",
+                        target_path.display()
+                    );
+                    return Ok(());
+                }
+                OverwriteMode::Backup => {
+                    if dry_run {
+                        info!("Dry-run, not backing up existing {}", target_path.display());
+                    } else {
+                        backup_existing(&target_path)?;
+                    }
+                }
+            },
         }
     }
 
@@ -163,17 +671,24 @@ fn move_photo(photo: &Photo, move_file: bool, dry_run: bool) -> Result<(), PsErr
     let original_path = photo.path().as_ref().unwrap();
 
     if move_file {
-        match std::fs::rename(original_path, &new_path) {
+        match std::fs::rename(original_path, &target_path) {
             Ok(_) => {}
             Err(err) => {
                 info!("Failed to move file: {}", err);
+                return Err(err.into());
             }
         }
     } else {
-        match std::fs::copy(original_path, &new_path) {
+        match std::fs::copy(original_path, &target_path) {
             Ok(_) => {}
             Err(err) => {
-                info!("Failed to copy {} -> {}: {}", original_path, &new_path, err);
+                info!(
+                    "Failed to copy {} -> {}: {}",
+                    original_path,
+                    target_path.display(),
+                    err
+                );
+                return Err(err.into());
             }
         }
     }
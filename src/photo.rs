@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+pub mod exif;
+
+/// A single photo (or video) discovered under the source directory, along
+/// with the date it was taken and the path it will be moved/copied to.
+#[derive(Debug, Clone)]
+pub struct Photo {
+    path: Option<String>,
+    new_path: Option<String>,
+    date: Option<DateTime<Utc>>,
+}
+
+impl Photo {
+    pub fn new(path: String, date: Option<DateTime<Utc>>) -> Self {
+        Photo {
+            path: Some(path),
+            new_path: None,
+            date,
+        }
+    }
+
+    pub fn path(&self) -> &Option<String> {
+        &self.path
+    }
+
+    pub fn new_path(&self) -> &Option<String> {
+        &self.new_path
+    }
+
+    pub fn set_new_path(&mut self, path: String) {
+        self.new_path = Some(path);
+    }
+
+    pub fn date(&self) -> &Option<DateTime<Utc>> {
+        &self.date
+    }
+
+    /// Computes a BLAKE3 digest over the file's bytes, used to detect exact
+    /// content duplicates regardless of file name or location.
+    pub fn content_digest(&self) -> io::Result<blake3::Hash> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "photo has no source path"))?;
+        let bytes = fs::read(path)?;
+        Ok(blake3::hash(&bytes))
+    }
+
+    /// Computes a perceptual dHash of the photo, used to catch near-dupes
+    /// (re-encodes, recompressions) that an exact content digest would miss.
+    /// Returns `None` for anything the `image` crate can't decode, e.g.
+    /// videos or RAW files.
+    pub fn compute_dhash(&self) -> Option<DHash> {
+        let path = self.path.as_ref()?;
+        let image = image::open(path).ok()?;
+        Some(DHash::compute(&image))
+    }
+}
+
+/// Tracks content digests (and, for images, perceptual hashes) of photos
+/// already placed during this run so that `convert_files` can skip or
+/// hard-link exact *and* near duplicates instead of silently overwriting the
+/// destination.
+#[derive(Debug, Default)]
+pub struct DedupTracker {
+    seen: HashMap<blake3::Hash, PathBuf>,
+    seen_hashes: Vec<(DHash, PathBuf)>,
+}
+
+impl DedupTracker {
+    pub fn new() -> Self {
+        DedupTracker::default()
+    }
+
+    /// Looks up `digest` among files already placed this run, without
+    /// recording anything. Returns the previously-placed path if found.
+    ///
+    /// Deliberately split from recording: the caller should only record a
+    /// photo's digest once its move/copy/link has actually succeeded, so a
+    /// failed write doesn't poison later true duplicates against a
+    /// destination that was never written.
+    pub fn lookup_digest(&self, digest: &blake3::Hash) -> Option<PathBuf> {
+        self.seen.get(digest).cloned()
+    }
+
+    /// Records that `digest` was placed at `dest`, so later calls to
+    /// `lookup_digest` find it.
+    pub fn record_digest(&mut self, digest: blake3::Hash, dest: PathBuf) {
+        self.seen.insert(digest, dest);
+    }
+
+    /// Like `lookup_digest`, but for the perceptual dHash of an image that
+    /// already passed the exact-digest check: looks up `hash` among dHashes
+    /// of images already placed this run, within `threshold` Hamming
+    /// distance, without recording anything.
+    pub fn lookup_near_duplicate(&self, hash: &DHash, threshold: u32) -> Option<PathBuf> {
+        self.seen_hashes
+            .iter()
+            .find(|(existing_hash, _)| hash.is_near_duplicate(existing_hash, threshold))
+            .map(|(_, existing_path)| existing_path.clone())
+    }
+
+    /// Records that `hash` was placed at `dest`, so later calls to
+    /// `lookup_near_duplicate` find it.
+    pub fn record_hash(&mut self, hash: DHash, dest: PathBuf) {
+        self.seen_hashes.push((hash, dest));
+    }
+}
+
+/// A perceptual hash used to detect near-duplicates across re-encodes or
+/// recompressions of the same image (e.g. the same photo saved by two
+/// different apps). Two images are considered near-duplicates when the
+/// Hamming distance between their dHashes is at most a configurable
+/// threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DHash(pub u64);
+
+impl DHash {
+    /// Computes the difference hash of `image`: downscale to 9x8 grayscale
+    /// and set bit `i` to 1 where `pixel[i] > pixel[i+1]` along each row.
+    pub fn compute(image: &image::DynamicImage) -> DHash {
+        let small = image.grayscale().resize_exact(
+            9,
+            8,
+            image::imageops::FilterType::Triangle,
+        );
+        let gray = small.to_luma8();
+
+        let mut bits: u64 = 0;
+        let mut i = 0;
+        for y in 0..8 {
+            for x in 0..8 {
+                let left = gray.get_pixel(x, y)[0];
+                let right = gray.get_pixel(x + 1, y)[0];
+                if left > right {
+                    bits |= 1 << i;
+                }
+                i += 1;
+            }
+        }
+        DHash(bits)
+    }
+
+    pub fn hamming_distance(&self, other: &DHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    pub fn is_near_duplicate(&self, other: &DHash, threshold: u32) -> bool {
+        self.hamming_distance(other) <= threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dedup_tracker_lookup_digest_finds_recorded_duplicate() {
+        let mut tracker = DedupTracker::new();
+        let digest = blake3::hash(b"same bytes");
+
+        assert_eq!(tracker.lookup_digest(&digest), None);
+        tracker.record_digest(digest, PathBuf::from("a.jpg"));
+        assert_eq!(
+            tracker.lookup_digest(&digest),
+            Some(PathBuf::from("a.jpg"))
+        );
+    }
+
+    #[test]
+    fn dedup_tracker_lookup_digest_ignores_unrecorded_photos() {
+        // A photo that was only looked up (never recorded, e.g. because its
+        // write failed) must not show up as a duplicate for a later photo.
+        let mut tracker = DedupTracker::new();
+        let digest = blake3::hash(b"same bytes");
+
+        assert_eq!(tracker.lookup_digest(&digest), None);
+        assert_eq!(tracker.lookup_digest(&digest), None);
+    }
+
+    #[test]
+    fn dhash_hamming_distance_is_zero_for_identical_hashes() {
+        let a = DHash(0b1010_1010);
+        let b = DHash(0b1010_1010);
+        assert_eq!(a.hamming_distance(&b), 0);
+        assert!(a.is_near_duplicate(&b, 0));
+    }
+
+    #[test]
+    fn dhash_hamming_distance_counts_differing_bits() {
+        let a = DHash(0b0000_0000);
+        let b = DHash(0b0000_0111);
+        assert_eq!(a.hamming_distance(&b), 3);
+        assert!(!a.is_near_duplicate(&b, 2));
+        assert!(a.is_near_duplicate(&b, 3));
+    }
+
+    #[test]
+    fn dedup_tracker_lookup_near_duplicate_within_threshold() {
+        let mut tracker = DedupTracker::new();
+        let a = DHash(0b0000_0000);
+        let b = DHash(0b0000_0011);
+
+        assert_eq!(tracker.lookup_near_duplicate(&a, 5), None);
+        tracker.record_hash(a, PathBuf::from("a.jpg"));
+        assert_eq!(
+            tracker.lookup_near_duplicate(&b, 5),
+            Some(PathBuf::from("a.jpg"))
+        );
+    }
+
+    #[test]
+    fn dedup_tracker_lookup_near_duplicate_outside_threshold_finds_nothing() {
+        let mut tracker = DedupTracker::new();
+        let a = DHash(0b0000_0000);
+        let b = DHash(0b0000_0011);
+
+        tracker.record_hash(a, PathBuf::from("a.jpg"));
+        assert_eq!(tracker.lookup_near_duplicate(&b, 1), None);
+    }
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// Parses the `DateTimeOriginal` tag out of a raw EXIF byte blob, as pulled
+/// from a HEIF container's metadata box or a RAW file's embedded EXIF
+/// segment. Returns `None` if the blob has no readable date.
+pub fn parse_capture_date(raw_exif: &[u8]) -> Option<DateTime<Utc>> {
+    let exif = exif::Reader::new()
+        .read_raw(raw_exif.to_vec())
+        .ok()?;
+    let field = exif.get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)?;
+    // EXIF DateTimeOriginal values are colon-separated ("YYYY:MM:DD HH:MM:SS"
+    // per the EXIF spec), not dash-separated. The raw ASCII value is read
+    // because `display_value()` reformats the date with dashes.
+    let raw = match field.value {
+        exif::Value::Ascii(ref values) => values.first()?,
+        _ => return None,
+    };
+    let text = std::str::from_utf8(raw).ok()?;
+    let naive = NaiveDateTime::parse_from_str(text.trim(), "%Y:%m:%d %H:%M:%S").ok()?;
+    Some(DateTime::from_naive_utc_and_offset(naive, Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATE_TIME_ORIGINAL_TAG: u16 = 0x9003;
+    const EXIF_IFD_POINTER_TAG: u16 = 0x8769;
+    const ASCII_TYPE: u16 = 2;
+    const LONG_TYPE: u16 = 4;
+
+    /// Builds a minimal raw EXIF blob (little-endian TIFF) with
+    /// `DateTimeOriginal` set to `value`. As in real EXIF data, IFD0 holds a
+    /// pointer to the Exif sub-IFD and the date lives in that sub-IFD, so the
+    /// blob can be fed straight into `parse_capture_date`.
+    fn raw_exif_with_datetime_original(value: &str) -> Vec<u8> {
+        let mut data = value.as_bytes().to_vec();
+        data.push(0); // NUL terminator, per the EXIF ASCII count convention
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"II"); // little-endian byte order
+        buf.extend_from_slice(&42u16.to_le_bytes());
+        buf.extend_from_slice(&8u32.to_le_bytes()); // offset to IFD0
+
+        // IFD0: one entry pointing at the Exif sub-IFD right after it.
+        buf.extend_from_slice(&1u16.to_le_bytes());
+        buf.extend_from_slice(&EXIF_IFD_POINTER_TAG.to_le_bytes());
+        buf.extend_from_slice(&LONG_TYPE.to_le_bytes());
+        buf.extend_from_slice(&1u32.to_le_bytes());
+        let exif_ifd_offset = (buf.len() + 4 + 4) as u32; // after this field + next-IFD pointer
+        buf.extend_from_slice(&exif_ifd_offset.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes()); // no next IFD
+
+        // Exif sub-IFD: the DateTimeOriginal entry.
+        buf.extend_from_slice(&1u16.to_le_bytes()); // one IFD entry
+        buf.extend_from_slice(&DATE_TIME_ORIGINAL_TAG.to_le_bytes());
+        buf.extend_from_slice(&ASCII_TYPE.to_le_bytes());
+        buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        let value_offset = (buf.len() + 4 + 4) as u32; // after this field + next-IFD pointer
+        buf.extend_from_slice(&value_offset.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes()); // no next IFD
+
+        buf.extend_from_slice(&data);
+        buf
+    }
+
+    #[test]
+    fn parse_capture_date_reads_colon_separated_exif_datetime() {
+        let raw = raw_exif_with_datetime_original("2024:01:15 10:30:00");
+        let parsed = parse_capture_date(&raw).expect("expected a parsed capture date");
+        assert_eq!(parsed.to_rfc3339(), "2024-01-15T10:30:00+00:00");
+    }
+
+    #[test]
+    fn parse_capture_date_rejects_garbage() {
+        assert_eq!(parse_capture_date(b"not a valid exif blob"), None);
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PsErrorKind {
+    IoError,
+    ConfigError,
+    MediaError,
+}
+
+#[derive(Debug)]
+pub struct PsError {
+    kind: PsErrorKind,
+    message: String,
+}
+
+impl PsError {
+    pub fn new(kind: PsErrorKind, message: String) -> Self {
+        PsError { kind, message }
+    }
+
+    pub fn kind(&self) -> PsErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for PsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for PsError {}
+
+impl From<std::io::Error> for PsError {
+    fn from(err: std::io::Error) -> Self {
+        PsError::new(PsErrorKind::IoError, err.to_string())
+    }
+}
+
+impl From<ffmpeg_next::Error> for PsError {
+    fn from(err: ffmpeg_next::Error) -> Self {
+        PsError::new(PsErrorKind::MediaError, err.to_string())
+    }
+}
+
+impl From<image::ImageError> for PsError {
+    fn from(err: image::ImageError) -> Self {
+        PsError::new(PsErrorKind::MediaError, err.to_string())
+    }
+}
@@ -0,0 +1,134 @@
+use super::*;
+
+/// Builds a fresh scratch directory under the OS temp dir for a single test,
+/// so tests can exercise real filesystem paths without clobbering each other.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("photosort-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn path_locks_serialize_same_path_access() {
+    let dir = scratch_dir("path-locks");
+    let target = dir.join("contended");
+
+    let locks = Arc::new(PathLocks::new());
+    let counter = Arc::new(AtomicU64::new(0));
+    let max_concurrent = Arc::new(AtomicU64::new(0));
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let locks = Arc::clone(&locks);
+            let counter = Arc::clone(&counter);
+            let max_concurrent = Arc::clone(&max_concurrent);
+            let target = target.clone();
+            std::thread::spawn(move || {
+                locks.with_lock(&target, || {
+                    let now = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_concurrent.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                    counter.fetch_sub(1, Ordering::SeqCst);
+                });
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(max_concurrent.load(Ordering::SeqCst), 1);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn path_locks_allow_different_paths_concurrently() {
+    let dir = scratch_dir("path-locks-distinct");
+    let locks = PathLocks::new();
+
+    locks.with_lock(&dir.join("a"), || {
+        // Locking an unrelated path must not deadlock against the first.
+        locks.with_lock(&dir.join("b"), || {});
+    });
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn numbered_suffix_path_picks_lowest_free_index() {
+    let dir = scratch_dir("numbered-suffix");
+    let path = dir.join("photo.jpg");
+    std::fs::write(&path, b"original").unwrap();
+    std::fs::write(format!("{}.~1~", path.display()), b"taken").unwrap();
+
+    let picked = numbered_suffix_path(&path);
+    assert_eq!(picked, PathBuf::from(format!("{}.~2~", path.display())));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn parse_prompt_answer_accepts_letters_and_full_words() {
+    assert_eq!(parse_prompt_answer("y"), Some(ParsedAnswer::Overwrite));
+    assert_eq!(parse_prompt_answer("Yes"), Some(ParsedAnswer::Overwrite));
+    assert_eq!(parse_prompt_answer("n"), Some(ParsedAnswer::Skip));
+    assert_eq!(parse_prompt_answer("r"), Some(ParsedAnswer::Rename));
+    assert_eq!(parse_prompt_answer("a"), Some(ParsedAnswer::OverwriteAll));
+    assert_eq!(
+        parse_prompt_answer("overwrite-all"),
+        Some(ParsedAnswer::OverwriteAll)
+    );
+    assert_eq!(parse_prompt_answer("l"), Some(ParsedAnswer::SkipAll));
+    assert_eq!(
+        parse_prompt_answer("skip-all"),
+        Some(ParsedAnswer::SkipAll)
+    );
+}
+
+#[test]
+fn parse_prompt_answer_rejects_ambiguous_bare_all() {
+    // "all" used to be accepted as overwrite-all, which clobbered files for
+    // anyone who typed it expecting skip-all from the "skip-a[l]l" hint.
+    assert_eq!(parse_prompt_answer("all"), None);
+    assert_eq!(parse_prompt_answer("ALL"), None);
+}
+
+#[test]
+fn live_photo_companion_finds_same_named_mov() {
+    let dir = scratch_dir("live-photo");
+    let still = dir.join("IMG_1234.heic");
+    std::fs::write(&still, b"not a real heic").unwrap();
+    std::fs::write(dir.join("IMG_1234.mov"), b"not a real mov").unwrap();
+
+    let photo = Photo::new(still.to_string_lossy().to_string(), None);
+    assert_eq!(live_photo_companion(&photo), Some(dir.join("IMG_1234.mov")));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn live_photo_companion_is_none_without_a_matching_mov() {
+    let dir = scratch_dir("live-photo-none");
+    let still = dir.join("IMG_5678.heic");
+    std::fs::write(&still, b"not a real heic").unwrap();
+
+    let photo = Photo::new(still.to_string_lossy().to_string(), None);
+    assert_eq!(live_photo_companion(&photo), None);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn backup_existing_renames_to_lowest_free_index() {
+    let dir = scratch_dir("backup-existing");
+    let path = dir.join("photo.jpg");
+    std::fs::write(&path, b"original").unwrap();
+
+    backup_existing(&path).unwrap();
+
+    assert!(!path.exists());
+    assert!(Path::new(&format!("{}.~1~", path.display())).exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
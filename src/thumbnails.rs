@@ -0,0 +1,109 @@
+use std::path::{Path, PathBuf};
+
+use chrono::Datelike;
+use ffmpeg_next as ffmpeg;
+
+use crate::photo::Photo;
+use crate::pserror::error::*;
+
+/// How far into a video to seek for the frame used as its thumbnail.
+const THUMBNAIL_OFFSET_SECONDS: f64 = 1.0;
+
+/// Decodes a frame near `THUMBNAIL_OFFSET_SECONDS` into `photo`'s source
+/// video and writes it as a JPEG under `dest_dir/thumbnails/year/month/day/`,
+/// mirroring the date-based layout used for the sorted files themselves.
+pub fn generate_thumbnail(photo: &Photo, dest_dir: &str) -> Result<(), PsError> {
+    let video_path = photo
+        .path()
+        .as_ref()
+        .ok_or_else(|| PsError::new(PsErrorKind::IoError, "photo has no source path".to_string()))?;
+    generate_thumbnail_from(video_path, photo, dest_dir)
+}
+
+/// Like `generate_thumbnail`, but decodes `video_path` instead of `photo`'s
+/// own source file. Used for Live Photos, where the still image (`photo`)
+/// carries the destination path/date but the motion video lives in a
+/// separate sibling file.
+pub fn generate_thumbnail_from(video_path: &str, photo: &Photo, dest_dir: &str) -> Result<(), PsError> {
+    let thumb_path = thumbnail_path(dest_dir, photo)?;
+    if let Some(dir) = thumb_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut input = ffmpeg::format::input(&video_path)?;
+    let stream = input
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or_else(|| PsError::new(PsErrorKind::MediaError, format!("No video stream in {}", video_path)))?;
+    let stream_index = stream.index();
+
+    let context = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+    let mut decoder = context.decoder().video()?;
+
+    let mut scaler = ffmpeg::software::scaling::Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::format::Pixel::RGB24,
+        decoder.width(),
+        decoder.height(),
+        ffmpeg::software::scaling::Flags::BILINEAR,
+    )?;
+
+    // `Input::seek` takes timestamps in ffmpeg's global AV_TIME_BASE units,
+    // not in the stream's own time base.
+    let target_ts = (THUMBNAIL_OFFSET_SECONDS / f64::from(ffmpeg::rescale::TIME_BASE)) as i64;
+    input.seek(target_ts, ..target_ts)?;
+
+    for (stream, packet) in input.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+        decoder.send_packet(&packet)?;
+        let mut frame = ffmpeg::util::frame::Video::empty();
+        if decoder.receive_frame(&mut frame).is_ok() {
+            let mut rgb_frame = ffmpeg::util::frame::Video::empty();
+            scaler.run(&frame, &mut rgb_frame)?;
+            return write_jpeg(&rgb_frame, &thumb_path);
+        }
+    }
+
+    Err(PsError::new(
+        PsErrorKind::MediaError,
+        format!("Could not decode a frame from {}", video_path),
+    ))
+}
+
+fn thumbnail_path(dest_dir: &str, photo: &Photo) -> Result<PathBuf, PsError> {
+    let new_path = photo.new_path().as_ref().ok_or_else(|| {
+        PsError::new(
+            PsErrorKind::IoError,
+            "photo has no destination path yet".to_string(),
+        )
+    })?;
+    let date = photo.date().ok_or_else(|| {
+        PsError::new(PsErrorKind::IoError, "photo has no capture date".to_string())
+    })?;
+    let name = Path::new(new_path)
+        .file_name()
+        .ok_or_else(|| PsError::new(PsErrorKind::IoError, format!("Invalid path: {}", new_path)))?;
+
+    Ok(Path::new(dest_dir)
+        .join("thumbnails")
+        .join(format!("{}", date.year()))
+        .join(format!("{:02}", date.month()))
+        .join(format!("{:02}", date.day()))
+        .join(format!("{}.thumb.jpg", name.to_string_lossy())))
+}
+
+fn write_jpeg(frame: &ffmpeg::util::frame::Video, path: &Path) -> Result<(), PsError> {
+    let image = image::RgbImage::from_raw(frame.width(), frame.height(), frame.data(0).to_vec())
+        .ok_or_else(|| {
+            PsError::new(
+                PsErrorKind::MediaError,
+                "decoded frame buffer has the wrong size".to_string(),
+            )
+        })?;
+    image.save(path)?;
+    Ok(())
+}
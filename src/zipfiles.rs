@@ -0,0 +1,12 @@
+use log::warn;
+
+use crate::config::configurator::Config;
+
+/// Entry point for a `.zip` archive given as `--src`. Zip import is not
+/// implemented: this only logs a warning, and no photo is read from the
+/// archive or sorted into the destination.
+pub fn process_zip_file(path: &String, _config: &Config) {
+    // TODO: extract entries to a temp directory and hand them to the same
+    // discovery/move pipeline used for directory sources.
+    warn!("Zip archive import for {} is not yet implemented", path);
+}